@@ -2,20 +2,34 @@
 //!
 //!
 //! This library is designed to create .wav and .ot files for the Elektron Octatrack by
-//! concatenating other audio samples (.wav) and settings each added file as a slice in the final
-//! file.
+//! concatenating other audio samples (.wav, .ogg, .flac) and settings each added file as a slice
+//! in the final file.
 
 use std::fs;
-use std::io::Write;
+use std::io::{Read as _, Write};
 use std::path::Path;
 
+extern crate claxon;
 extern crate hound;
+extern crate lewton;
+extern crate ogg;
+
+/// Byte offset of the first slice record within the .ot data buffer
+const OT_SLICES_OFFSET: usize = 58;
+/// Size in bytes of a single slice record (start, end, loop point, loop mode, timestretch mode)
+const OT_SLICE_LEN: usize = 20;
+/// Total size in bytes of a generated .ot file: preamble + 64 slice slots + slice count + checksum
+const OT_FILE_LEN: usize = OT_SLICES_OFFSET + 64 * OT_SLICE_LEN + 4 + 2;
 
 /// Struct used for the individual slices
 pub struct OTSlice {
     pub loop_point: u32,
     pub start_point: u32,
     pub length: u32,
+    /// Per-slice loop mode; falls back to `Slicer::loop_mode` when `None`
+    pub loop_mode: Option<u32>,
+    /// Per-slice timestretch mode; falls back to `Slicer::timestretch_mode` when `None`
+    pub timestretch_mode: Option<u32>,
 }
 
 /// The Slicer struct is the main struct of the library and it's responsable for parsing .wav files and generating the final .wav and .ot files
@@ -30,12 +44,25 @@ pub struct Slicer {
     pub slices: Vec<OTSlice>,
     // List of files to be processed
     pub filelist: Vec<std::path::PathBuf>,
-    // Determines if the output file will be stereo or mono (not implemented yet)
+    /// Determines if the final .wav file is written as interleaved stereo (true) or mono (false)
     pub stereo: bool,
+    /// When enabled, files whose sample rate doesn't match `sample_rate` are converted
+    /// (via Catmull-Rom interpolation) instead of being rejected by `add_file`
+    pub resample: bool,
     max_file_length: usize,
     start_offset: u32,
     /// Tempo / BPM of the final .wav file
     pub tempo: u32,
+    /// Default gain for the chain (Octatrack's factory default is 48, i.e. 0 dB)
+    pub gain: u16,
+    /// Global loop mode for the chain (0 = off, 1 = on), used by slices that don't set their
+    /// own `OTSlice::loop_mode`
+    pub loop_mode: u32,
+    /// Global timestretch mode for the chain (0 = off, 2 = normal, 3 = beat), used by slices
+    /// that don't set their own `OTSlice::timestretch_mode`
+    pub timestretch_mode: u32,
+    /// Quantize setting for the chain (255 = off, the Octatrack's default)
+    pub quantize: u8,
 
     pub data_buffer: Vec<u8>,
 }
@@ -51,7 +78,12 @@ impl Slicer {
             start_offset: 0,
             max_file_length: 0,
             stereo: false,
+            resample: false,
             tempo,
+            gain: 48,
+            loop_mode: 0,
+            timestretch_mode: 0,
+            quantize: 255,
             data_buffer: Vec::new(),
         }
     }
@@ -67,7 +99,12 @@ impl Slicer {
             start_offset: 0,
             max_file_length: 0,
             stereo: false,
+            resample: false,
             tempo: 124,
+            gain: 48,
+            loop_mode: 0,
+            timestretch_mode: 0,
+            quantize: 255,
             data_buffer: Vec::new(),
         }
     }
@@ -85,27 +122,40 @@ impl Slicer {
         let path = std::path::PathBuf::from(filepath.clone());
         println!("Adding file to list: {}", filepath);
 
-        // Define valid sample format
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: self.sample_rate.clone(),
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-
         // Check if file exists
         match path.is_file() {
             true => {
-                // Open file
-                let mut reader = hound::WavReader::open(filepath).unwrap();
-                // Check if file specs are valid
-                if reader.spec() == spec {
-                    let samples: Vec<i16> = reader.samples().map(|s| s.unwrap()).collect();
-
-                    // Check if file length is greater than max_file_length (used for creating evenly spaced sample chains)
-                    let total_samples = samples.len();
-                    if total_samples > self.max_file_length {
-                        self.max_file_length = total_samples;
+                let format = detect_format(&path);
+                let (spec, in_frames) = match probe_audio_file(&path, format) {
+                    Ok(probed) => probed,
+                    Err(_) => return Err("Invalid file (failed to read audio header)"),
+                };
+
+                // Mono/stereo WAV input is validated exactly, like before; compressed formats
+                // are downmixed to the active channel count in process_file, so any channel
+                // count is accepted here.
+                let channels_ok = match format {
+                    InputFormat::Wav => spec.channels == 1 || (self.stereo && spec.channels == 2),
+                    InputFormat::Ogg | InputFormat::Flac => true,
+                };
+                // A mismatched sample rate is only accepted when resampling is turned on;
+                // process_file will convert it to self.sample_rate.
+                let rate_ok = spec.sample_rate == self.sample_rate || self.resample;
+                let format_ok = match format {
+                    InputFormat::Wav => {
+                        spec.bits_per_sample == 16 && spec.sample_format == hound::SampleFormat::Int
+                    }
+                    // process_file requantizes Ogg/FLAC decode output to 16-bit as it streams
+                    InputFormat::Ogg | InputFormat::Flac => true,
+                };
+
+                if channels_ok && rate_ok && format_ok {
+                    // Check if file length (in frames, after resampling) is greater than
+                    // max_file_length (used for creating evenly spaced sample chains)
+                    let total_frames =
+                        in_frames * self.sample_rate as u64 / spec.sample_rate as u64;
+                    if total_frames as usize > self.max_file_length {
+                        self.max_file_length = total_frames as usize;
                     }
 
                     // Add file path to list of files to be processed
@@ -127,49 +177,127 @@ impl Slicer {
     ) -> Result<&'static str, Box<dyn std::error::Error>> {
         println!("Processing file: {}", filepath.display());
 
-        // Define valid sample format
+        let out_channels: u16 = if self.stereo { 2 } else { 1 };
+
+        // Define valid sample format for the concat file
         let spec = hound::WavSpec {
-            channels: 1,
+            channels: out_channels,
             sample_rate: self.sample_rate,
             bits_per_sample: 16,
             sample_format: hound::SampleFormat::Int,
         };
 
         if self.slices.len() < 65 {
-            // Open file
-            let mut reader = hound::WavReader::open(filepath)?;
-
-            // Return array of samples (i16)
-            let samples: Vec<i16> = reader.samples::<i16>().collect::<Result<Vec<_>, _>>()?;
-
             // Create path for temporary concat file
             let output_folder_path: &Path = self.output_folder.as_ref();
             let wav_file_name = format!("{}.wav", self.output_filename);
             let temp_file_path = Path::join(output_folder_path, wav_file_name);
 
-            let slice_len: u32 = match temp_file_path.is_file() {
-                true => {
-                    // Append samples if temporary files already exists
-                    let temp_wav_file = hound::WavWriter::append(temp_file_path)?;
-                    self.fill_wav_file(temp_wav_file, samples, evenly_spaced)
+            let writer = match temp_file_path.is_file() {
+                // Append samples if temporary files already exists
+                true => hound::WavWriter::append(temp_file_path)?,
+                // Create new file (based on specified specs) and add samples
+                false => hound::WavWriter::create(temp_file_path, spec)?,
+            };
+
+            // Decode and stream one frame at a time: WAV via hound, Ogg Vorbis via lewton, FLAC via claxon
+            let format = detect_format(&filepath);
+
+            let frame_count = match format {
+                InputFormat::Wav => {
+                    let mut reader = hound::WavReader::open(&filepath)?;
+                    let in_channels = reader.spec().channels;
+                    let in_rate = reader.spec().sample_rate;
+                    // hound already tracks this independent of read position; no extra pass needed.
+                    let in_frames = reader.duration() as u64;
+                    let mut samples = reader.samples::<i16>();
+                    let next_frame = || -> Result<Option<Vec<i16>>, Box<dyn std::error::Error>> {
+                        next_native_frame(in_channels, || samples.next().transpose().map_err(Into::into))
+                    };
+                    self.stream_frames_to_writer(
+                        writer,
+                        InputStreamInfo { channels: in_channels, rate: in_rate, frames: in_frames },
+                        out_channels,
+                        evenly_spaced,
+                        next_frame,
+                    )?
+                }
+                InputFormat::Ogg => {
+                    let ogg_file = fs::File::open(&filepath)?;
+                    let mut ogg_reader = lewton::inside_ogg::OggStreamReader::new(ogg_file)?;
+                    let in_channels = ogg_reader.ident_hdr.audio_channels as u16;
+                    let in_rate = ogg_reader.ident_hdr.audio_sample_rate;
+                    // probe_ogg does a full page scan; only pay for it when resampling needs in_frames.
+                    let in_frames = if in_rate == self.sample_rate {
+                        0
+                    } else {
+                        probe_ogg(&filepath)?.1
+                    };
+                    let mut pending: std::collections::VecDeque<i16> = std::collections::VecDeque::new();
+                    let next_frame = || -> Result<Option<Vec<i16>>, Box<dyn std::error::Error>> {
+                        while pending.len() < in_channels as usize {
+                            match ogg_reader.read_dec_packet_itl()? {
+                                Some(packet) => pending.extend(packet),
+                                None => return Ok(None),
+                            }
+                        }
+                        Ok(Some(pending.drain(..in_channels as usize).collect()))
+                    };
+                    self.stream_frames_to_writer(
+                        writer,
+                        InputStreamInfo { channels: in_channels, rate: in_rate, frames: in_frames },
+                        out_channels,
+                        evenly_spaced,
+                        next_frame,
+                    )?
                 }
-                false => {
-                    // Create new file (based on specified specs) and add samples
-                    let temp_wav_file = hound::WavWriter::create(temp_file_path, spec)?;
-                    self.fill_wav_file(temp_wav_file, samples, evenly_spaced)
+                InputFormat::Flac => {
+                    let mut flac_reader = claxon::FlacReader::open(&filepath)?;
+                    let info = flac_reader.streaminfo();
+                    let in_channels = info.channels as u16;
+                    let in_rate = info.sample_rate;
+                    // STREAMINFO already carries the frame count; no extra read needed. When it is
+                    // unset, the resampler runs until the stream itself ends.
+                    let in_frames = info.samples.unwrap_or(u64::MAX);
+                    // Signed so lower bit depths (e.g. 8-bit) scale up instead of no-op'ing
+                    let shift = info.bits_per_sample as i32 - 16;
+                    let mut samples = flac_reader.samples();
+                    let next_frame = || -> Result<Option<Vec<i16>>, Box<dyn std::error::Error>> {
+                        next_native_frame(in_channels, || {
+                            samples
+                                .next()
+                                .transpose()
+                                .map(|sample| {
+                                    sample.map(|s| {
+                                        (if shift >= 0 { s >> shift } else { s << -shift }) as i16
+                                    })
+                                })
+                                .map_err(Into::into)
+                        })
+                    };
+                    self.stream_frames_to_writer(
+                        writer,
+                        InputStreamInfo { channels: in_channels, rate: in_rate, frames: in_frames },
+                        out_channels,
+                        evenly_spaced,
+                        next_frame,
+                    )?
                 }
             };
 
-            // Create new slice and append it to slices vector
+            // Create new slice and append it to slices vector. Octatrack slice points are
+            // frame-based, so start/length/loop must count frames, not raw (interleaved) samples.
             let new_ot_slice = OTSlice {
                 start_point: self.start_offset,
-                length: slice_len,
-                loop_point: slice_len,
+                length: frame_count,
+                loop_point: frame_count,
+                loop_mode: None,
+                timestretch_mode: None,
             };
             self.slices.push(new_ot_slice);
 
-            // Add sample length to start offset
-            self.start_offset += slice_len;
+            // Add frame length to start offset
+            self.start_offset += frame_count;
 
             Ok("File successfully parsed.")
         } else {
@@ -177,30 +305,65 @@ impl Slicer {
         }
     }
 
-    fn fill_wav_file(
+    /// Streams one file through decode -> downmix -> resample -> write and returns the frame
+    /// count written (not counting evenly-spaced padding)
+    fn stream_frames_to_writer(
         &mut self,
         mut writer: hound::WavWriter<std::io::BufWriter<std::fs::File>>,
-        samples: Vec<i16>,
+        input: InputStreamInfo,
+        out_channels: u16,
         evenly_spaced: bool,
-    ) -> u32 {
-        if evenly_spaced {
-            // Write samples
-            for &sample in samples.iter() {
-                writer.write_sample(sample).expect("Failed to write sample");
-            }
-            // Pad with zeros
-            for _ in samples.len()..self.max_file_length {
-                writer.write_sample(0).expect("Failed to write sample");
+        mut next_frame: impl FnMut() -> Result<Option<Vec<i16>>, Box<dyn std::error::Error>>,
+    ) -> Result<u32, Box<dyn std::error::Error>> {
+        let InputStreamInfo { channels: in_channels, rate: in_rate, frames: in_frames } = input;
+        let mut frame_count: u32 = 0;
+
+        if in_rate == self.sample_rate {
+            while let Some(frame) = next_frame()? {
+                let frame = downmix_to_channels(&frame, in_channels, out_channels);
+                for sample in frame {
+                    writer.write_sample(sample)?;
+                }
+                frame_count += 1;
             }
         } else {
-            // Only write actual samples
-            for &sample in samples.iter() {
-                writer.write_sample(sample).expect("Failed to write sample");
+            let mut decode_error: Option<Box<dyn std::error::Error>> = None;
+            let mut pull_downmixed = || -> Option<Vec<i16>> {
+                match next_frame() {
+                    Ok(Some(frame)) => Some(downmix_to_channels(&frame, in_channels, out_channels)),
+                    Ok(None) => None,
+                    Err(err) => {
+                        decode_error = Some(err);
+                        None
+                    }
+                }
+            };
+
+            let mut resampler =
+                StreamingResampler::new(out_channels as usize, in_rate, self.sample_rate, in_frames);
+            while let Some(frame) = resampler.next_frame(&mut pull_downmixed) {
+                for sample in frame {
+                    writer.write_sample(sample)?;
+                }
+                frame_count += 1;
+            }
+
+            if let Some(err) = decode_error {
+                return Err(err);
+            }
+        }
+
+        if evenly_spaced {
+            // Pad with zeros, one frame at a time, up to the longest file in the chain
+            for _ in frame_count as usize..self.max_file_length {
+                for _ in 0..out_channels {
+                    writer.write_sample(0)?;
+                }
             }
         }
 
-        writer.finalize().expect("Failed to finalize WAV");
-        samples.len() as u32
+        writer.finalize()?;
+        Ok(frame_count)
     }
     /// Generates the .ot file for the Octatrack and renames the concat .wav file to the same name as the .ot file
     pub fn generate_ot_file(&mut self, evenly_spaced: bool) -> Result<&'static str, &'static str> {
@@ -241,35 +404,44 @@ impl Slicer {
 
         println!("Total samples: {}", total_samples);
 
-        // Calculate the number of bars
-        let bars_mult: f32 = (124.0 * total_samples as f32) / (self.sample_rate * 60) as f32 + 0.5;
+        // Calculate the number of bars (using the chain's own tempo, not the Octatrack default)
+        let bars_mult: f32 =
+            (self.tempo as f32 * total_samples as f32) / (self.sample_rate * 60) as f32 + 0.5;
         let bars: u32 = bars_mult as u32 * 25;
 
         // Add data to the .ot buffer
         self.push_u32(tempo); // Tempo
-        self.push_u32(bars.clone()); // Trimlen
-        self.push_u32(bars.clone()); // loopLen
-        self.push_u32(0); // Stretch
-        self.push_u32(0); // Loop
-        self.push_u16(48); // Gain
-        self.data_buffer.push(255); // Quantize
+        self.push_u32(bars); // Trimlen
+        self.push_u32(bars); // loopLen
+        self.push_u32(self.timestretch_mode); // Stretch
+        self.push_u32(self.loop_mode); // Loop
+        self.push_u16(self.gain); // Gain
+        self.data_buffer.push(self.quantize); // Quantize
         self.push_u32(0); // trimStart
-        self.push_u32(total_samples.clone()); // trimEnd
+        self.push_u32(total_samples); // trimEnd
         self.push_u32(0); // loopPoint
 
         // Add data for each of the slices
         for i in 0..64 {
             if i < self.slices.len() {
-                let start = self.slices[i].start_point;
-                let len = self.slices[i].start_point + self.slices[i].length;
+                let slice = &self.slices[i];
+                let start = slice.start_point;
+                let len = slice.start_point + slice.length;
+                let loop_point = slice.loop_point;
+                let loop_mode = slice.loop_mode.unwrap_or(self.loop_mode);
+                let timestretch_mode = slice.timestretch_mode.unwrap_or(self.timestretch_mode);
                 println!("Adding slice - Start: {} - Length: {}", start, len);
                 self.push_u32(start);
                 self.push_u32(len);
-                self.push_u32(self.slices[i].loop_point);
+                self.push_u32(loop_point);
+                self.push_u32(loop_mode); // Per-slice loop mode
+                self.push_u32(timestretch_mode); // Per-slice timestretch
             } else {
                 self.push_u32(0);
                 self.push_u32(0);
                 self.push_u32(0);
+                self.push_u32(0);
+                self.push_u32(0);
             }
         }
 
@@ -311,6 +483,71 @@ impl Slicer {
         Ok("Temporary WAV file renamed succesfully.")
     }
 
+    /// Reads an existing .ot file (as written by `generate_ot_file`) and returns a new `Slicer`
+    /// with its tempo and slices reconstructed, so a chain can be re-sliced or merged
+    pub fn parse_ot_file<P: AsRef<Path>>(path: P) -> Result<Self, &'static str> {
+        let data = fs::read(path).map_err(|_| "Could not read .ot file.")?;
+
+        if data.len() != OT_FILE_LEN {
+            return Err("Invalid .ot file (unexpected length).");
+        }
+
+        // Checksum covers everything after the header, up to (not including) the checksum itself
+        let mut checksum: u16 = 0;
+        for &byte in &data[16..OT_FILE_LEN - 2] {
+            checksum += byte as u16;
+        }
+        if checksum != read_u16_be(&data, OT_FILE_LEN - 2) {
+            return Err("Invalid .ot file (checksum mismatch).");
+        }
+
+        let tempo = read_u32_be(&data, 23) / (6 * 4);
+        println!("Parsed tempo: {}", tempo);
+
+        let timestretch_mode = read_u32_be(&data, 35);
+        let loop_mode = read_u32_be(&data, 39);
+        let gain = read_u16_be(&data, 43);
+        let quantize = data[45];
+
+        let slice_count = read_u32_be(&data, OT_SLICES_OFFSET + 64 * OT_SLICE_LEN) as usize;
+        println!("Number of slices: {}", slice_count);
+        // The slot array only ever holds 64 records (see generate_ot_file); a file claiming more
+        // (crafted, corrupted, or from a chain that overflowed the 64-slice limit) must not be
+        // allowed to index past the slot table.
+        if slice_count > 64 {
+            return Err("Invalid .ot file (slice count exceeds 64).");
+        }
+
+        let mut slices = Vec::with_capacity(slice_count);
+        for i in 0..slice_count {
+            let slice_offset = OT_SLICES_OFFSET + i * OT_SLICE_LEN;
+            let start_point = read_u32_be(&data, slice_offset);
+            let end_point = read_u32_be(&data, slice_offset + 4);
+            let loop_point = read_u32_be(&data, slice_offset + 8);
+            let loop_mode = read_u32_be(&data, slice_offset + 12);
+            let timestretch_mode = read_u32_be(&data, slice_offset + 16);
+            if end_point < start_point {
+                return Err("Invalid .ot file (slice end point before start point).");
+            }
+            slices.push(OTSlice {
+                start_point,
+                length: end_point - start_point,
+                loop_point,
+                loop_mode: Some(loop_mode),
+                timestretch_mode: Some(timestretch_mode),
+            });
+        }
+
+        let mut slicer = Self::default();
+        slicer.tempo = tempo;
+        slicer.timestretch_mode = timestretch_mode;
+        slicer.loop_mode = loop_mode;
+        slicer.gain = gain;
+        slicer.quantize = quantize;
+        slicer.slices = slices;
+        Ok(slicer)
+    }
+
     fn push_u32(&mut self, num: u32) {
         let array = num.to_le_bytes();
         for i in 0..4 {
@@ -325,3 +562,406 @@ impl Slicer {
         // vector
     }
 }
+
+/// Reads a big-endian u32 out of `data` at `offset` (the .ot file's fields are big-endian,
+/// matching `Slicer::push_u32`)
+fn read_u32_be(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Reads a big-endian u16 out of `data` at `offset` (matching `Slicer::push_u16`)
+fn read_u16_be(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+/// An input file's native (pre-downmix) channel count, sample rate, and frame count, as passed
+/// to `Slicer::stream_frames_to_writer`
+struct InputStreamInfo {
+    channels: u16,
+    rate: u32,
+    frames: u64,
+}
+
+/// Input container/codec, detected from the file extension and falling back to magic bytes
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InputFormat {
+    Wav,
+    Ogg,
+    Flac,
+}
+
+/// Detects `path`'s audio format from its extension, falling back to the first 4 bytes
+fn detect_format(path: &Path) -> InputFormat {
+    match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase()) {
+        Some(ext) if ext == "ogg" => InputFormat::Ogg,
+        Some(ext) if ext == "flac" => InputFormat::Flac,
+        Some(ext) if ext == "wav" => InputFormat::Wav,
+        _ => {
+            let mut magic = [0u8; 4];
+            match fs::File::open(path).and_then(|mut file| file.read_exact(&mut magic)) {
+                Ok(()) if &magic == b"OggS" => InputFormat::Ogg,
+                Ok(()) if &magic == b"fLaC" => InputFormat::Flac,
+                _ => InputFormat::Wav,
+            }
+        }
+    }
+}
+
+/// Reads `path`'s spec and frame count without decoding any samples, dispatching on `format`
+fn probe_audio_file(
+    path: &Path,
+    format: InputFormat,
+) -> Result<(hound::WavSpec, u64), Box<dyn std::error::Error>> {
+    match format {
+        InputFormat::Wav => probe_wav(path),
+        InputFormat::Ogg => probe_ogg(path),
+        InputFormat::Flac => probe_flac(path),
+    }
+}
+
+/// Reads a WAV file's header only; `hound` exposes the frame count directly.
+fn probe_wav(path: &Path) -> Result<(hound::WavSpec, u64), Box<dyn std::error::Error>> {
+    let reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    Ok((spec, reader.duration() as u64))
+}
+
+/// Reads the Ogg identification header for the spec, then walks the page structure (no Vorbis
+/// decoding) for the last page's absolute granule position, i.e. the total frame count
+fn probe_ogg(path: &Path) -> Result<(hound::WavSpec, u64), Box<dyn std::error::Error>> {
+    let ident_file = fs::File::open(path)?;
+    let ogg_reader = lewton::inside_ogg::OggStreamReader::new(ident_file)?;
+    let spec = hound::WavSpec {
+        channels: ogg_reader.ident_hdr.audio_channels as u16,
+        sample_rate: ogg_reader.ident_hdr.audio_sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let page_file = fs::File::open(path)?;
+    let mut packet_reader = ogg::PacketReader::new(page_file);
+    let mut total_frames: u64 = 0;
+    while let Some(packet) = packet_reader.read_packet()? {
+        total_frames = packet.absgp_page();
+    }
+
+    Ok((spec, total_frames))
+}
+
+/// Reads a FLAC file's STREAMINFO block, which already carries the total sample (frame) count.
+fn probe_flac(path: &Path) -> Result<(hound::WavSpec, u64), Box<dyn std::error::Error>> {
+    let flac_reader = claxon::FlacReader::open(path)?;
+    let info = flac_reader.streaminfo();
+    let spec = hound::WavSpec {
+        channels: info.channels as u16,
+        sample_rate: info.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    Ok((spec, info.samples.unwrap_or(0)))
+}
+
+/// Pulls `channels` samples via `pull_sample` and assembles them into one interleaved frame,
+/// dropping a trailing partial frame (if any) at end of stream.
+fn next_native_frame(
+    channels: u16,
+    mut pull_sample: impl FnMut() -> Result<Option<i16>, Box<dyn std::error::Error>>,
+) -> Result<Option<Vec<i16>>, Box<dyn std::error::Error>> {
+    let mut frame = Vec::with_capacity(channels as usize);
+    for _ in 0..channels {
+        match pull_sample()? {
+            Some(sample) => frame.push(sample),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(frame))
+}
+
+/// Downmixes (by averaging) or duplicates interleaved samples so they end up with `out_channels`
+/// channels per frame; a no-op when the channel counts already match
+fn downmix_to_channels(samples: &[i16], in_channels: u16, out_channels: u16) -> Vec<i16> {
+    if in_channels == out_channels {
+        return samples.to_vec();
+    }
+
+    let in_channels = in_channels as usize;
+    let mono: Vec<i16> = samples
+        .chunks(in_channels)
+        .map(|frame| {
+            let sum: i32 = frame.iter().map(|&sample| sample as i32).sum();
+            (sum / in_channels as i32) as i16
+        })
+        .collect();
+
+    if out_channels == 1 {
+        mono
+    } else {
+        // out_channels == 2: duplicate the downmixed mono signal across both channels
+        mono.into_iter().flat_map(|sample| [sample, sample]).collect()
+    }
+}
+
+/// Catmull-Rom resampler from `src_rate` to `target_rate` that pulls source frames on demand
+/// and keeps only the small sliding window the interpolation needs, instead of buffering the
+/// whole file
+struct StreamingResampler {
+    channels: usize,
+    src_rate: u64,
+    target_rate: u64,
+    /// Precomputed output length, `floor(in_frames * target_rate / src_rate)`; `next_frame`
+    /// also stops early if the source runs dry before that many frames are produced
+    out_frames: u64,
+    /// Frames already pulled from the source, indexed by `window_start + i`
+    window: Vec<Vec<i16>>,
+    window_start: i64,
+    source_exhausted: bool,
+    out_index: u64,
+}
+
+impl StreamingResampler {
+    fn new(channels: usize, src_rate: u32, target_rate: u32, in_frames: u64) -> Self {
+        StreamingResampler {
+            channels,
+            src_rate: src_rate as u64,
+            target_rate: target_rate as u64,
+            out_frames: in_frames.saturating_mul(target_rate as u64) / src_rate as u64,
+            window: Vec::new(),
+            window_start: 0,
+            source_exhausted: false,
+            out_index: 0,
+        }
+    }
+
+    /// Pulls source frames until `index` is present in the window (or the source is exhausted)
+    fn fill_up_to(&mut self, index: i64, pull: &mut impl FnMut() -> Option<Vec<i16>>) {
+        while !self.source_exhausted && self.window_start + self.window.len() as i64 <= index {
+            match pull() {
+                Some(frame) => self.window.push(frame),
+                None => self.source_exhausted = true,
+            }
+        }
+    }
+
+    /// Returns the frame at `index`, clamping to the nearest available edge frame
+    fn frame_at(&self, index: i64) -> &[i16] {
+        let last = self.window_start + self.window.len() as i64 - 1;
+        let clamped = index.clamp(self.window_start, last);
+        &self.window[(clamped - self.window_start) as usize]
+    }
+
+    fn next_frame(&mut self, pull: &mut impl FnMut() -> Option<Vec<i16>>) -> Option<Vec<i16>> {
+        if self.out_index >= self.out_frames {
+            return None;
+        }
+
+        let src_pos = self.out_index * self.src_rate;
+        let i1 = (src_pos / self.target_rate) as i64;
+        let f = (src_pos % self.target_rate) as f32 / self.target_rate as f32;
+
+        self.fill_up_to(i1 + 2, pull);
+        if self.window.is_empty() {
+            return None;
+        }
+        // out_frames is only an estimate (a truncated WAV header or an unset FLAC
+        // total-sample count can overstate the real source); once the source has
+        // actually run dry and i1 has moved past the last real frame, stop instead
+        // of repeating the clamped tail frame out to the estimated count.
+        if self.source_exhausted && i1 > self.window_start + self.window.len() as i64 - 1 {
+            return None;
+        }
+
+        let mut out = Vec::with_capacity(self.channels);
+        for channel in 0..self.channels {
+            let y0 = self.frame_at(i1 - 1)[channel] as f32;
+            let y1 = self.frame_at(i1)[channel] as f32;
+            let y2 = self.frame_at(i1 + 1)[channel] as f32;
+            let y3 = self.frame_at(i1 + 2)[channel] as f32;
+
+            let interpolated = y1
+                + 0.5
+                    * f
+                    * ((y2 - y0)
+                        + f * ((2.0 * y0 - 5.0 * y1 + 4.0 * y2 - y3)
+                            + f * (3.0 * (y1 - y2) + y3 - y0)));
+
+            out.push(interpolated.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16);
+        }
+
+        self.out_index += 1;
+
+        // Evict frames before i1 - 1: no future call needs them, since i1 only grows
+        let keep_from = i1 - 1;
+        if keep_from > self.window_start {
+            let drop_count = (keep_from - self.window_start) as usize;
+            self.window.drain(..drop_count.min(self.window.len()));
+            self.window_start = keep_from;
+        }
+
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod resampler_tests {
+    use super::StreamingResampler;
+
+    /// Drains a `StreamingResampler` fed from a fixed list of mono source frames.
+    fn resample_all(src: &[i16], src_rate: u32, target_rate: u32) -> Vec<i16> {
+        let mut iter = src.iter().map(|&s| vec![s]);
+        let mut pull = move || iter.next();
+        let mut resampler = StreamingResampler::new(1, src_rate, target_rate, src.len() as u64);
+        let mut out = Vec::new();
+        while let Some(frame) = resampler.next_frame(&mut pull) {
+            out.push(frame[0]);
+        }
+        out
+    }
+
+    #[test]
+    fn passthrough_when_rates_match() {
+        let src = [0, 1000, -500, 2000, 3000];
+        assert_eq!(resample_all(&src, 44100, 44100), src);
+    }
+
+    #[test]
+    fn exact_on_linear_ramp() {
+        // Catmull-Rom reduces to plain linear interpolation on collinear points, so resampling
+        // a ramp reproduces it exactly (no overshoot/undershoot) wherever no edge frame is
+        // clamped, as is the case for every output at this ratio.
+        let src: Vec<i16> = (0..10).map(|i| i * 100).collect();
+        let out = resample_all(&src, 3, 2);
+        assert_eq!(out, vec![0, 150, 300, 450, 600, 750]);
+    }
+
+    #[test]
+    fn matches_hand_computed_value_mid_window() {
+        // y_i = 100 * i^2, sampled away from the window edges so no clamping kicks in.
+        let src: Vec<i16> = (0..6).map(|i| 100 * i * i).collect();
+        let out = resample_all(&src, 3, 2);
+        // out_index=1 lands at source position 1.5 (i1=1, f=0.5); hand-derived via the
+        // Catmull-Rom formula from y0=0, y1=100, y2=400, y3=900.
+        assert_eq!(out[1], 225);
+    }
+}
+
+#[cfg(test)]
+mod ot_file_tests {
+    use super::{fs, OTSlice, Slicer, OT_SLICES_OFFSET, OT_SLICE_LEN};
+
+    /// Recomputes and patches the trailing checksum after a test mutates the data buffer directly
+    fn fix_checksum(data: &mut [u8]) {
+        let len = data.len();
+        let mut checksum: u16 = 0;
+        for &byte in &data[16..len - 2] {
+            checksum += byte as u16;
+        }
+        let bytes = checksum.to_be_bytes();
+        data[len - 2] = bytes[0];
+        data[len - 1] = bytes[1];
+    }
+
+    /// Writes a valid .ot file (via `generate_ot_file`) for `filename` and returns its path
+    fn write_valid_ot_file(filename: &str, slices: Vec<OTSlice>) -> std::path::PathBuf {
+        let mut slicer = Slicer::new(slices, 44100, 120);
+        slicer.output_folder = std::env::temp_dir().to_string_lossy().into_owned();
+        slicer.output_filename = filename.to_string();
+        slicer.gain = 40;
+        slicer.quantize = 3;
+        slicer.loop_mode = 1;
+        slicer.timestretch_mode = 2;
+        slicer.generate_ot_file(false).unwrap();
+        std::path::Path::new(&slicer.output_folder).join(format!("{filename}.ot"))
+    }
+
+    #[test]
+    fn generate_then_parse_round_trip() {
+        let slices = vec![
+            OTSlice {
+                start_point: 0,
+                length: 1000,
+                loop_point: 0,
+                loop_mode: Some(1),
+                timestretch_mode: Some(2),
+            },
+            OTSlice {
+                start_point: 1000,
+                length: 500,
+                loop_point: 100,
+                loop_mode: None,
+                timestretch_mode: None,
+            },
+        ];
+        let path = write_valid_ot_file("ot_utils_test_roundtrip", slices);
+        let parsed = Slicer::parse_ot_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(parsed.tempo, 120);
+        assert_eq!(parsed.gain, 40);
+        assert_eq!(parsed.quantize, 3);
+        assert_eq!(parsed.loop_mode, 1);
+        assert_eq!(parsed.timestretch_mode, 2);
+        assert_eq!(parsed.slices.len(), 2);
+        assert_eq!(parsed.slices[0].start_point, 0);
+        assert_eq!(parsed.slices[0].length, 1000);
+        assert_eq!(parsed.slices[0].loop_mode, Some(1));
+        assert_eq!(parsed.slices[1].start_point, 1000);
+        assert_eq!(parsed.slices[1].length, 500);
+        assert_eq!(parsed.slices[1].loop_point, 100);
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        let path = std::env::temp_dir().join("ot_utils_test_bad_length.ot");
+        fs::write(&path, vec![0u8; 10]).unwrap();
+        let result = Slicer::parse_ot_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_checksum_mismatch() {
+        let path = write_valid_ot_file("ot_utils_test_bad_checksum", Vec::new());
+        let mut data = fs::read(&path).unwrap();
+        data[20] ^= 0xFF; // corrupt a byte covered by the checksum without fixing it back up
+        fs::write(&path, &data).unwrap();
+
+        let result = Slicer::parse_ot_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_slice_count_over_64() {
+        let path = write_valid_ot_file("ot_utils_test_slicecount", Vec::new());
+        let mut data = fs::read(&path).unwrap();
+        let slice_count_offset = OT_SLICES_OFFSET + 64 * OT_SLICE_LEN;
+        data[slice_count_offset..slice_count_offset + 4].copy_from_slice(&65u32.to_be_bytes());
+        fix_checksum(&mut data);
+        fs::write(&path, &data).unwrap();
+
+        let result = Slicer::parse_ot_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_end_point_before_start_point() {
+        let slices = vec![OTSlice {
+            start_point: 0,
+            length: 10,
+            loop_point: 0,
+            loop_mode: Some(0),
+            timestretch_mode: Some(0),
+        }];
+        let path = write_valid_ot_file("ot_utils_test_end_before_start", slices);
+        let mut data = fs::read(&path).unwrap();
+        // Slice 0's start_point field; push it past the (unchanged) end_point of 10
+        data[OT_SLICES_OFFSET..OT_SLICES_OFFSET + 4].copy_from_slice(&20u32.to_be_bytes());
+        fix_checksum(&mut data);
+        fs::write(&path, &data).unwrap();
+
+        let result = Slicer::parse_ot_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}